@@ -0,0 +1,34 @@
+use lightningcss::printer::PrinterOptions;
+use lightningcss::stylesheet::{MinifyOptions, ParserOptions, StyleSheet};
+use lightningcss::targets::{Browsers, Targets};
+
+/// Parses a comma-separated browserslist-style query (e.g. `"last 2 versions, > 0.5%"`) into
+/// lightningcss's target representation.
+pub fn parse_targets(query: &str) -> Option<Browsers> {
+    Browsers::from_browserslist([query]).ok().flatten()
+}
+
+/// Parses, minifies, and (when `targets` is set) down-levels `source` for those browser targets.
+pub fn minify(source: &str, targets: Option<Browsers>) -> Result<String, String> {
+    let mut stylesheet =
+        StyleSheet::parse(source, ParserOptions::default()).map_err(|err| err.to_string())?;
+
+    let targets = Targets::from(targets.unwrap_or_default());
+
+    stylesheet
+        .minify(MinifyOptions {
+            targets,
+            ..MinifyOptions::default()
+        })
+        .map_err(|err| err.to_string())?;
+
+    let printed = stylesheet
+        .to_css(PrinterOptions {
+            minify: true,
+            targets,
+            ..PrinterOptions::default()
+        })
+        .map_err(|err| err.to_string())?;
+
+    Ok(printed.code)
+}
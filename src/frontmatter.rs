@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+
+/// Metadata parsed from a page's leading `---` (YAML) or `+++` (TOML) front-matter block.
+#[derive(Debug, Default, Clone)]
+pub struct FrontMatter {
+    pub title: Option<String>,
+    pub template: Option<String>,
+    pub draft: bool,
+    pub fields: HashMap<String, String>,
+}
+
+/// Splits a leading front-matter block off `content`, returning its parsed fields (if any) and
+/// the remaining markdown body. Only a flat `key: value` / `key = value` mapping is supported,
+/// which covers the title/template/draft fields mdmake actually reads.
+pub fn split(content: &str) -> (Option<FrontMatter>, &str) {
+    for fence in ["---", "+++"] {
+        let Some(rest) = content.strip_prefix(fence) else {
+            continue;
+        };
+        let rest = rest.strip_prefix('\n').unwrap_or(rest);
+
+        if let Some(end) = rest.find(&format!("\n{fence}")) {
+            let block = &rest[..end];
+            let after_fence = &rest[end + 1 + fence.len()..];
+            let body = after_fence.strip_prefix('\n').unwrap_or(after_fence);
+            return (Some(parse_fields(block)), body);
+        }
+    }
+
+    (None, content)
+}
+
+fn parse_fields(block: &str) -> FrontMatter {
+    let mut front_matter = FrontMatter::default();
+
+    for line in block.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once([':', '=']) else {
+            continue;
+        };
+
+        let key = key.trim();
+        let value = value.trim().trim_matches('"').trim_matches('\'').to_string();
+
+        match key {
+            "title" => front_matter.title = Some(value),
+            "template" => front_matter.template = Some(value),
+            "draft" => front_matter.draft = value == "true",
+            _ => {
+                front_matter.fields.insert(key.to_string(), value);
+            }
+        }
+    }
+
+    front_matter
+}
+
+/// Cheaply checks whether a file is marked `draft: true`, without rendering the rest of it.
+pub fn is_draft(content: &str) -> bool {
+    split(content).0.is_some_and(|front_matter| front_matter.draft)
+}
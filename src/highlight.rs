@@ -0,0 +1,122 @@
+use std::sync::OnceLock;
+
+use markdown::mdast;
+use regex::Regex;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::ThemeSet;
+use syntect::html::{
+    styled_line_to_highlighted_html, ClassStyle, ClassedHTMLGenerator, IncludeBackground,
+};
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
+
+/// Special `--highlight-theme` value meaning "emit class-based spans instead of inline styles".
+pub const CSS_THEME: &str = "css";
+
+/// Theme whose colors back the class-based `highlight.css` companion stylesheet in `css` mode.
+const CSS_THEME_BASIS: &str = "base16-ocean.dark";
+
+static SYNTAX_SET: OnceLock<SyntaxSet> = OnceLock::new();
+static THEME_SET: OnceLock<ThemeSet> = OnceLock::new();
+
+fn syntax_set() -> &'static SyntaxSet {
+    SYNTAX_SET.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn theme_set() -> &'static ThemeSet {
+    THEME_SET.get_or_init(ThemeSet::load_defaults)
+}
+
+/// Whether `name` is a usable `--highlight-theme` value: either the `css` sentinel or a theme
+/// bundled with `syntect`'s default `ThemeSet`.
+pub fn theme_exists(name: &str) -> bool {
+    name == CSS_THEME || theme_set().themes.contains_key(name)
+}
+
+/// The stylesheet to write alongside the output when `highlight_theme` is set to `css`.
+pub fn css_theme_stylesheet() -> String {
+    syntect::html::css_for_theme_with_class_style(
+        &theme_set().themes[CSS_THEME_BASIS],
+        ClassStyle::SpacedPrefixed { prefix: "hl-" },
+    )
+    .unwrap_or_default()
+}
+
+/// Collects the `lang`/`value` of every `Code` node in document order, matching the order
+/// `markdown::to_html` emits `<pre><code>` blocks in.
+fn collect_code_blocks(ast: &mdast::Node) -> Vec<(Option<String>, String)> {
+    let mut blocks = Vec::new();
+
+    fn walk(node: &mdast::Node, blocks: &mut Vec<(Option<String>, String)>) {
+        if let mdast::Node::Code(code) = node {
+            blocks.push((code.lang.clone(), code.value.clone()));
+        }
+        if let Some(children) = node.children() {
+            for child in children {
+                walk(child, blocks);
+            }
+        }
+    }
+
+    walk(ast, &mut blocks);
+    blocks
+}
+
+/// Replaces every plain `<pre><code>` block that `markdown::to_html` produced with a
+/// syntax-highlighted rendering, in document order. Unknown or missing languages fall back to
+/// plain-text rendering rather than panicking.
+pub fn highlight_code_blocks(html: &str, ast: &mdast::Node, theme_name: &str) -> String {
+    let mut blocks = collect_code_blocks(ast).into_iter();
+    if blocks.len() == 0 {
+        return html.to_string();
+    }
+
+    let code_block_re =
+        Regex::new(r#"(?s)<pre><code(?: class="language-([^"]+)")?>.*?</code></pre>"#).unwrap();
+
+    code_block_re
+        .replace_all(html, |caps: &regex::Captures| {
+            let Some((lang, value)) = blocks.next() else {
+                return caps[0].to_string();
+            };
+            render_code_block(&value, lang.as_deref(), theme_name)
+        })
+        .to_string()
+}
+
+fn render_code_block(value: &str, lang: Option<&str>, theme_name: &str) -> String {
+    let ss = syntax_set();
+    let syntax = lang
+        .and_then(|token| ss.find_syntax_by_token(token))
+        .unwrap_or_else(|| ss.find_syntax_plain_text());
+
+    if theme_name == CSS_THEME {
+        let mut generator = ClassedHTMLGenerator::new_with_class_style(
+            syntax,
+            ss,
+            ClassStyle::SpacedPrefixed { prefix: "hl-" },
+        );
+        for line in LinesWithEndings::from(value) {
+            let _ = generator.parse_html_for_line_which_includes_newline(line);
+        }
+        format!("<pre><code>{}</code></pre>", generator.finalize())
+    } else {
+        let theme = &theme_set().themes[theme_name];
+        let mut highlighter = HighlightLines::new(syntax, theme);
+        let mut body = String::new();
+        for line in LinesWithEndings::from(value) {
+            let Ok(ranges) = highlighter.highlight_line(line, ss) else {
+                body.push_str(line);
+                continue;
+            };
+            let Ok(line_html) =
+                styled_line_to_highlighted_html(&ranges[..], IncludeBackground::Yes)
+            else {
+                body.push_str(line);
+                continue;
+            };
+            body.push_str(&line_html);
+        }
+        format!(r#"<pre><code>{}</code></pre>"#, body)
+    }
+}
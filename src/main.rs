@@ -1,25 +1,46 @@
 use clap::{arg, command, Command};
 use markdown::{mdast, ParseOptions};
+use rayon::prelude::*;
 use regex::Regex;
 
 use std::{
-    collections::{hash_map::Entry, HashMap},
+    collections::HashMap,
     fs,
-    io::{self, Write},
+    io,
     iter,
-    path::PathBuf,
-    time::{Duration, SystemTime},
+    path::{Path, PathBuf},
+    time::SystemTime,
 };
 
+mod css;
+mod frontmatter;
+mod highlight;
+mod search;
+mod serve;
+mod summary;
+mod template;
+mod toc;
+mod watch;
+
 fn main() {
     let command = cli();
     let config = Config::from(&command);
     let matches = command.get_matches();
 
-    if matches.subcommand().is_some_and(|(cmd, _)| cmd == "watch") {
-        watch_mode(&config);
-    } else {
-        compile_all(&config);
+    match matches.subcommand() {
+        Some(("watch", _)) => watch_mode(&config),
+        Some(("serve", serve_matches)) => {
+            let address = serve_matches
+                .get_one::<String>("address")
+                .expect("--address has a default value.");
+            let port: u16 = serve_matches
+                .get_one::<String>("port")
+                .expect("--port has a default value.")
+                .parse()
+                .expect("--port must be a valid port number.");
+            serve_mode(&config, address, port);
+        }
+        _ => compile_all(&config),
     }
 }
 
@@ -31,20 +52,49 @@ fn cli() -> Command {
                 .short_flag('w')
                 .about("Watch for changes and automatically recompile."),
         )
+        .subcommand(
+            Command::new("serve")
+                .short_flag('s')
+                .about("Compile, serve the output directory locally, and live-reload on change.")
+                .arg(arg!(--port [PORT] "Port for the local dev server.").default_value("8080"))
+                .arg(
+                    arg!(--address [ADDRESS] "Address for the local dev server.")
+                        .default_value("127.0.0.1"),
+                ),
+        )
         .arg(arg!(-i --input [DIRECTORY] "The project root of the markdown files."))
         .arg(arg!(-o --output [DIRECTORY] "The destination for the compiled webpage."))
         .arg(arg!(--style [FILE] "The CSS-stylesheet to use for all html files."))
         .arg(arg!(--header [FILE] "The HTML-header to prepend to all HTML-Bodies."))
         .arg(arg!(--footer [FILE] "The HTML-footer to append to all HTML-Bodies."))
+        .arg(arg!(--"highlight-theme" [THEME] "Syntax-highlighting theme for code blocks (e.g. base16-ocean.dark), or 'css' to emit class-based spans plus a highlight.css."))
+        .arg(arg!(--toc "Generate a table of contents from each page's headings."))
+        .arg(arg!(--search "Build a client-side full-text search index over all compiled pages."))
+        .arg(arg!(--drafts "Also compile pages whose front matter sets 'draft: true'."))
+        .arg(arg!(--template [NAME] "Default Handlebars page template (from templates/*.hbs under the input directory), overridable per-page via a 'template' front-matter field."))
+        .arg(arg!(--"minify-css" "Minify the CSS-stylesheet, falling back to a plain copy if it fails to parse."))
+        .arg(arg!(--"css-targets" [QUERY] "Browserslist-style query (e.g. 'last 2 versions') of browsers the minified CSS must stay compatible with."))
+        .arg(arg!(--clean "Remove the output directory and recompile every page, instead of only the ones whose source changed."))
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Config {
     input_dir: PathBuf,
     output_dir: PathBuf,
     stylesheet: Option<PathBuf>,
     header: Option<String>,
     footer: Option<String>,
+    highlight_theme: Option<String>,
+    toc: bool,
+    search: bool,
+    drafts: bool,
+    live_reload: bool,
+    templates: template::Templates,
+    default_template: Option<String>,
+    site_structure: Vec<summary::PageEntry>,
+    minify_css: bool,
+    css_targets: Option<lightningcss::targets::Browsers>,
+    clean: bool,
 }
 
 impl Config {
@@ -99,142 +149,274 @@ impl Config {
                     }
                 }
             },
+            highlight_theme: matches.get_one::<String>("highlight-theme").map(|theme| {
+                if !highlight::theme_exists(theme) {
+                    eprintln!("error: unknown --highlight-theme '{theme}'.\n\nUsage: mdmake [OPTIONS] [COMMAND]\n\nFor more information, try '--help'.");
+                    std::process::exit(1);
+                }
+                theme.clone()
+            }),
+            toc: matches.get_flag("toc"),
+            search: matches.get_flag("search"),
+            drafts: matches.get_flag("drafts"),
+            live_reload: matches.subcommand().is_some_and(|(cmd, _)| cmd == "serve"),
+            templates: template::Templates::load(&input_dir.join("templates")),
+            default_template: matches.get_one::<String>("template").cloned(),
+            site_structure: summary::load(&input_dir),
+            minify_css: matches.get_flag("minify-css"),
+            css_targets: matches
+                .get_one::<String>("css-targets")
+                .and_then(|query| css::parse_targets(query)),
+            clean: matches.get_flag("clean"),
         }
     }
 }
 
 fn watch_mode(config: &Config) {
     compile_all(config);
-    copy_stylesheet_to_output_dir(config);
-
-    let mut last_modified_times = HashMap::new();
+    watch::watch(config, || {});
+}
 
-    if let Ok(paths) = walk_dir(&config.input_dir) {
-        for path in paths {
-            let time = fs::metadata(&path)
-                .and_then(|data| data.modified())
-                .unwrap_or(SystemTime::UNIX_EPOCH);
+fn serve_mode(config: &Config, address: &str, port: u16) {
+    compile_all(config);
 
-            last_modified_times.insert(path, time);
-        }
-    }
+    let generation = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
 
-    loop {
-        let walk_result = walk_dir(&config.input_dir);
-        if walk_result.is_err() {
-            continue;
-        }
+    let server_config = config.clone();
+    let server_generation = generation.clone();
+    let server_address = address.to_string();
+    std::thread::spawn(move || serve::run(&server_config, &server_address, port, server_generation));
 
-        let paths = walk_result.unwrap();
-        for path in paths {
-            if let Ok(metadata) = fs::metadata(&path) {
-                let modified_time = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
-
-                match last_modified_times.entry(path.clone()) {
-                    Entry::Occupied(entry) => {
-                        if modified_time > *entry.get() {
-                            println!("File has been modified: {}!", path.to_str().unwrap());
-                            println!("Recompiling...");
-                            compile_file(path.clone(), config);
-                        }
-                    }
-                    Entry::Vacant(entry) => {
-                        entry.insert(modified_time);
-                        println!("New File has been added: {}!", path.to_str().unwrap());
-                        println!("Compiling...");
-                        compile_file(path.clone(), config);
-                    }
-                }
-            }
-        }
-        std::thread::sleep(Duration::from_secs(1));
-    }
+    watch::watch(config, || {
+        generation.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    });
 }
 
+/// Compiles every page under `input_dir`. By default this is incremental: a markdown file is
+/// only recompiled, and a resource file only re-copied, when its source is newer than the
+/// matching file already in `output_dir`. Passing `--clean` wipes `output_dir` first and
+/// recompiles everything instead.
 fn compile_all(config: &Config) {
-    let _ = fs::remove_dir_all(&config.output_dir);
+    if config.clean {
+        let _ = fs::remove_dir_all(&config.output_dir);
+    }
     let _ = fs::create_dir_all(&config.output_dir);
 
     copy_stylesheet_to_output_dir(config);
+    write_highlight_stylesheet(config);
 
-    fn compile_all_recurse(subdir: &PathBuf, config: &Config) {
-        for entry in fs::read_dir(subdir).unwrap() {
-            let path = entry.unwrap().path();
-            if path.is_dir() {
-                compile_all_recurse(&path, config);
-            } else {
-                let output_relative_path = config
-                    .output_dir
-                    .join(path.strip_prefix(&config.input_dir).unwrap());
+    let Ok(paths) = walk_dir(&config.input_dir) else {
+        return;
+    };
 
-                if let Some(parent) = output_relative_path.parent() {
-                    fs::create_dir_all(parent)
-                        .expect("Failed to create necessary subdirectory in output directory.");
-                }
+    for path in &paths {
+        let output_relative_path = config
+            .output_dir
+            .join(path.strip_prefix(&config.input_dir).unwrap());
 
-                if path.extension().unwrap_or_default().to_ascii_lowercase() == "md" {
-                    compile_file(path, config);
-                } else {
-                    fs::copy(path, output_relative_path)
-                        .expect("Failed to copy resource file to output directory.");
-                }
-            }
+        if let Some(parent) = output_relative_path.parent() {
+            fs::create_dir_all(parent)
+                .expect("Failed to create necessary subdirectory in output directory.");
+        }
+    }
+
+    let (markdown_paths, resource_paths): (Vec<PathBuf>, Vec<PathBuf>) = paths
+        .into_iter()
+        .partition(|path| path.extension().unwrap_or_default().to_ascii_lowercase() == "md");
+
+    // A non-clean build still recompiles every page when `--search` is on, since the search
+    // index has to stay consistent across the whole site; the staleness check otherwise only
+    // applies when there's no cross-page index to keep in sync.
+    let to_compile: Vec<PathBuf> = markdown_paths
+        .into_iter()
+        .filter(|path| {
+            let is_draft =
+                fs::read_to_string(path).is_ok_and(|content| frontmatter::is_draft(&content));
+            !is_draft || config.drafts
+        })
+        .filter(|path| config.clean || config.search || is_stale(path, config))
+        .collect();
+
+    let pages: Vec<search::PageDoc> = to_compile
+        .into_par_iter()
+        .filter_map(|path| compile_file(path, config))
+        .collect();
+
+    for path in resource_paths {
+        if !config.clean && !is_stale(&path, config) {
+            continue;
         }
+
+        let output_relative_path = config
+            .output_dir
+            .join(path.strip_prefix(&config.input_dir).unwrap());
+        fs::copy(&path, output_relative_path)
+            .expect("Failed to copy resource file to output directory.");
+    }
+
+    if config.search {
+        search::write_index(config, &pages);
     }
+}
+
+/// Whether `source` (under `input_dir`) is newer than its already-compiled counterpart in
+/// `output_dir`, i.e. whether it needs recompiling/re-copying in an incremental build.
+fn is_stale(source: &Path, config: &Config) -> bool {
+    let relative = source
+        .strip_prefix(&config.input_dir)
+        .expect("File that was tried to compile seems to not be within the input directory.");
+    let output_relative = if relative.extension().and_then(|ext| ext.to_str()) == Some("md") {
+        relative.with_extension("html")
+    } else {
+        relative.to_path_buf()
+    };
+    let output_path = config.output_dir.join(output_relative);
+
+    let source_modified = fs::metadata(source)
+        .and_then(|data| data.modified())
+        .unwrap_or(SystemTime::UNIX_EPOCH);
 
-    compile_all_recurse(&config.input_dir, config)
+    match fs::metadata(&output_path).and_then(|data| data.modified()) {
+        Ok(output_modified) => source_modified > output_modified,
+        Err(_) => true,
+    }
 }
 
-fn compile_file(input_file: PathBuf, config: &Config) {
+fn compile_file(input_file: PathBuf, config: &Config) -> Option<search::PageDoc> {
     let output_file_relative = input_file
         .strip_prefix(&config.input_dir)
         .expect("File that was tried to compile seems to not be within the input directory.")
         .with_extension("html");
 
-    let output_file = config.output_dir.join(output_file_relative.clone());
-    fs::File::create(&output_file).expect("Failed to clear/create output HTML-file.");
+    let file_contents =
+        fs::read_to_string(&input_file).expect("Failed to read input MD file to String.");
+    let (front_matter, markdown) = frontmatter::split(&file_contents);
+
+    let ast = markdown::to_mdast(markdown, &ParseOptions::default()).unwrap();
 
-    let mut out_fd = fs::OpenOptions::new()
-        .append(true)
-        .open(&output_file)
-        .expect("Couldn't open output HTML-file for appending.");
+    let page_title = front_matter
+        .as_ref()
+        .and_then(|fm| fm.title.clone())
+        .or_else(|| get_page_title(&ast));
+
+    let md_contents = replace_md_link_extensions_with_html(markdown);
+    let main_body = markdown::to_html(&md_contents);
+    let main_body = match &config.highlight_theme {
+        Some(theme) => highlight::highlight_code_blocks(&main_body, &ast, theme),
+        None => main_body,
+    };
+
+    let (main_body, toc_nav) = if config.toc {
+        let headings = toc::collect_headings(&ast);
+        let main_body = toc::inject_heading_ids(&main_body, &headings);
+        let toc_nav = toc::render_nav(&headings);
+        (main_body, toc_nav)
+    } else {
+        (main_body, String::new())
+    };
+
+    let relative_root = relative_root(&output_file_relative);
+    let output_url = output_file_relative
+        .to_str()
+        .expect("Failed to convert PathBuf to String.")
+        .to_string();
+
+    let sidebar = summary::render_sidebar(&config.site_structure, &relative_root);
+    let prev_next = summary::render_prev_next(&config.site_structure, &output_url, &relative_root);
+
+    let template_name = front_matter
+        .as_ref()
+        .and_then(|fm| fm.template.clone())
+        .or_else(|| config.default_template.clone());
+    let no_fields = HashMap::new();
+    let front_matter_fields = front_matter.as_ref().map_or(&no_fields, |fm| &fm.fields);
+
+    let templated = template_name.as_deref().and_then(|name| {
+        config.templates.render(
+            name,
+            page_title.as_deref().unwrap_or(""),
+            &main_body,
+            &toc_nav,
+            &relative_root,
+            &sidebar,
+            &prev_next,
+            front_matter_fields,
+        )
+    });
+
+    let page_html = match templated {
+        Some(page) if config.live_reload => match page.rfind("</body>") {
+            Some(end) => format!("{}{}{}", &page[..end], serve::RELOAD_SCRIPT, &page[end..]),
+            None => page + serve::RELOAD_SCRIPT,
+        },
+        Some(page) => page,
+        None => {
+            let main_body = if main_body.contains(toc::MARKER) {
+                main_body.replace(toc::MARKER, &toc_nav)
+            } else if config.toc {
+                toc_nav + &main_body
+            } else {
+                main_body
+            };
+            render_manual_page(
+                config,
+                page_title.as_deref(),
+                &output_file_relative,
+                &sidebar,
+                &main_body,
+                &prev_next,
+            )
+        }
+    };
 
-    writeln!(out_fd, "<head>").expect("Failed writing HTML <head> opening tag to output file.");
+    let output_file = config.output_dir.join(&output_file_relative);
+    fs::write(&output_file, page_html).expect("Failed to write output HTML file.");
 
-    let ast = markdown::to_mdast(
-        &fs::read_to_string(&input_file).expect("Failed to read input MD file to String."),
-        &ParseOptions::default(),
-    )
-    .unwrap();
+    config
+        .search
+        .then(|| search::extract_page_doc(&ast, output_url, page_title))
+}
 
-    if let Some(title_tag) = get_file_title_html_tag(ast) {
-        write!(out_fd, "{}", title_tag).expect("Failed writing HTML <title> tag to output file.");
+/// Builds a page the old way: `<head>`/header/body/footer concatenated directly, used whenever
+/// no Handlebars template resolves for the page.
+fn render_manual_page(
+    config: &Config,
+    title: Option<&str>,
+    output_file_relative: &Path,
+    sidebar: &str,
+    body: &str,
+    prev_next: &str,
+) -> String {
+    let mut page = String::from("<head>\n");
+
+    if let Some(title) = title {
+        page.push_str(&format!("<title>{title}</title>\n"));
     }
 
     if let Some(style_link_tag) = get_html_style_link_tag(config, output_file_relative) {
-        write!(out_fd, "{}", style_link_tag)
-            .expect(r#"Failed writing HTML <link rel="stylesheet"> tag to output file."#);
+        page.push_str(&style_link_tag);
     }
 
-    writeln!(out_fd, "\n</head>\n<body>")
-        .expect("Failed writing </head> closing tag and <body> opening tag to HTML output file.");
+    page.push_str("\n</head>\n<body>\n");
 
     if let Some(header) = &config.header {
-        write!(out_fd, "{}", header)
-            .expect("Failed writing header file contents to output HTML file.");
+        page.push_str(header);
     }
 
-    let markdown = fs::read_to_string(input_file).expect("Failed to read input MD file to String.");
-
-    let md_contents = replace_md_link_extensions_with_html(&markdown);
-    let main_body = markdown::to_html(&md_contents);
-    write!(out_fd, "{}", main_body).expect("Couldn't append HTML-body to output HTML-file.");
+    page.push_str(sidebar);
+    page.push_str(body);
+    page.push_str(prev_next);
 
     if let Some(footer) = &config.footer {
-        write!(out_fd, "{}", footer)
-            .expect("Failed writing footer file contents to output HTML file.");
+        page.push_str(footer);
     }
-    write!(out_fd, "</body>").expect("Failed writing HTML </body> closing tag to output file.");
+
+    if config.live_reload {
+        page.push_str(serve::RELOAD_SCRIPT);
+    }
+
+    page.push_str("</body>");
+    page
 }
 
 fn walk_dir(start_dir: &PathBuf) -> io::Result<Vec<PathBuf>> {
@@ -276,17 +458,44 @@ fn replace_md_link_extensions_with_html(markdown: &str) -> String {
 }
 
 fn copy_stylesheet_to_output_dir(config: &Config) {
-    if let Some(stylesheet) = &config.stylesheet {
-        let exists = fs::metadata(stylesheet).is_ok();
-
-        if exists {
-            fs::copy(stylesheet, config.output_dir.join("style.css"))
-                .expect("Failed to copy CSS-stylesheet to root of output directory.");
+    let Some(stylesheet) = &config.stylesheet else {
+        return;
+    };
+
+    let Ok(contents) = fs::read_to_string(stylesheet) else {
+        return;
+    };
+
+    let output_css = if config.minify_css {
+        match css::minify(&contents, config.css_targets) {
+            Ok(minified) => minified,
+            Err(err) => {
+                eprintln!(
+                    "warning: failed to minify '{}': {err}. Copying it as-is instead.",
+                    stylesheet.display()
+                );
+                contents
+            }
         }
+    } else {
+        contents
+    };
+
+    fs::write(config.output_dir.join("style.css"), output_css)
+        .expect("Failed to copy CSS-stylesheet to root of output directory.");
+}
+
+fn write_highlight_stylesheet(config: &Config) {
+    if config.highlight_theme.as_deref() == Some(highlight::CSS_THEME) {
+        fs::write(
+            config.output_dir.join("highlight.css"),
+            highlight::css_theme_stylesheet(),
+        )
+        .expect("Failed to write highlight.css to output directory.");
     }
 }
 
-fn get_file_title_html_tag(ast: mdast::Node) -> Option<String> {
+fn get_page_title(ast: &mdast::Node) -> Option<String> {
     let mut page_title = None;
     let children = ast.children();
 
@@ -302,22 +511,25 @@ fn get_file_title_html_tag(ast: mdast::Node) -> Option<String> {
     }
 
     page_title
-        .is_some()
-        .then(|| format!("<title>{}</title>\n", page_title.unwrap()))
 }
 
-fn get_html_style_link_tag(config: &Config, output_file_relative: PathBuf) -> Option<String> {
+fn get_html_style_link_tag(config: &Config, output_file_relative: &Path) -> Option<String> {
     config.stylesheet.as_ref()?;
+    Some(format!(
+        r#"<link rel="stylesheet" href="{}style.css">"#,
+        relative_root(output_file_relative)
+    ))
+}
 
-    let relative_href = iter::repeat("../")
-        .take(output_file_relative.components().count() - 1)
-        .chain(iter::once("style.css"))
-        .collect::<String>();
-
-    Some(format!(r#"<link rel="stylesheet" href="{relative_href}">"#))
+/// The `../`-repeated prefix needed to reach `output_dir`'s root from a compiled page at
+/// `output_file_relative`.
+fn relative_root(output_file_relative: &Path) -> String {
+    iter::repeat("../")
+        .take(output_file_relative.components().count().saturating_sub(1))
+        .collect()
 }
 
-trait StringContents {
+pub(crate) trait StringContents {
     fn get_string_contents(&self) -> String;
 }
 
@@ -331,6 +543,11 @@ impl StringContents for mdast::Node {
             Self::Paragraph(e) => e.children.get_string_contents(),
             Self::Emphasis(e) => e.children.get_string_contents(),
             Self::Strong(e) => e.children.get_string_contents(),
+            Self::Heading(e) => e.children.get_string_contents(),
+            Self::List(e) => e.children.get_string_contents(),
+            Self::ListItem(e) => e.children.get_string_contents(),
+            Self::BlockQuote(e) => e.children.get_string_contents(),
+            Self::Code(e) => e.value.clone(),
             _ => String::new(),
         }
     }
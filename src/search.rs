@@ -0,0 +1,189 @@
+use std::collections::BTreeMap;
+
+use markdown::mdast;
+
+use crate::{Config, StringContents};
+
+/// Everything the search index needs to know about one compiled page.
+pub struct PageDoc {
+    url: String,
+    title: String,
+    text: String,
+}
+
+/// Extracts the flattened plain-text content of a page for indexing. `url` is the
+/// output-relative path and `title` the page's title (from front matter, else its H1), if any.
+pub fn extract_page_doc(ast: &mdast::Node, url: String, title: Option<String>) -> PageDoc {
+    let text = ast
+        .children()
+        .map(StringContents::get_string_contents)
+        .unwrap_or_default();
+
+    PageDoc {
+        title: title.unwrap_or_else(|| url.clone()),
+        url,
+        text,
+    }
+}
+
+/// Lowercases, strips punctuation, and drops 1-character tokens. Kept identical to the JS-side
+/// tokenizer in `SEARCH_JS` so matches line up.
+fn tokenize(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|ch: char| !ch.is_alphanumeric())
+        .filter(|token| token.chars().count() > 1)
+        .map(str::to_string)
+        .collect()
+}
+
+/// Builds an inverted index (`{ token -> [doc_ids] }`) plus a `docs` array of
+/// `{url, title, excerpt}` over every compiled `page`, and writes it as `searchindex.json`
+/// alongside a small search widget (`search.js`) into `output_dir`.
+pub fn write_index(config: &Config, pages: &[PageDoc]) {
+    let mut index: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+
+    let tokens_by_page: Vec<Vec<String>> = pages.iter().map(|page| tokenize(&page.text)).collect();
+    for (doc_id, tokens) in tokens_by_page.iter().enumerate() {
+        for token in tokens {
+            let doc_ids = index.entry(token.as_str()).or_default();
+            if doc_ids.last() != Some(&doc_id) {
+                doc_ids.push(doc_id);
+            }
+        }
+    }
+
+    let docs_json = pages
+        .iter()
+        .map(|page| {
+            format!(
+                r#"{{"url":{},"title":{},"excerpt":{}}}"#,
+                json_string(&page.url),
+                json_string(&page.title),
+                json_string(&excerpt(&page.text)),
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let index_json = index
+        .iter()
+        .map(|(token, doc_ids)| {
+            let ids = doc_ids
+                .iter()
+                .map(usize::to_string)
+                .collect::<Vec<_>>()
+                .join(",");
+            format!("{}:[{ids}]", json_string(token))
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let json = format!(r#"{{"docs":[{docs_json}],"index":{{{index_json}}}}}"#);
+
+    std::fs::write(config.output_dir.join("searchindex.json"), json)
+        .expect("Failed to write searchindex.json to output directory.");
+    std::fs::write(config.output_dir.join("search.js"), SEARCH_JS)
+        .expect("Failed to write search.js to output directory.");
+    std::fs::write(config.output_dir.join("search.html"), SEARCH_HTML)
+        .expect("Failed to write search.html to output directory.");
+}
+
+fn excerpt(text: &str) -> String {
+    const MAX_LEN: usize = 160;
+    if text.len() <= MAX_LEN {
+        return text.to_string();
+    }
+
+    let mut end = MAX_LEN;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}…", &text[..end])
+}
+
+fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Loads `searchindex.json`, tokenizes the query identically to the Rust side, and renders a
+/// results list into any element with `id="mdmake-search-results"` next to an
+/// `id="mdmake-search-input"` search box.
+const SEARCH_JS: &str = r#"(function () {
+    function tokenize(text) {
+        return text
+            .toLowerCase()
+            .split(/[^\p{Alphabetic}\p{N}]+/u)
+            .filter(function (token) { return Array.from(token).length > 1; });
+    }
+
+    function render(results, index) {
+        var list = document.getElementById("mdmake-search-results");
+        if (!list) return;
+        list.innerHTML = "";
+
+        results.forEach(function (docId) {
+            var doc = index.docs[docId];
+            var item = document.createElement("li");
+            var link = document.createElement("a");
+            link.href = doc.url;
+            link.textContent = doc.title;
+            item.appendChild(link);
+            var excerpt = document.createElement("p");
+            excerpt.textContent = doc.excerpt;
+            item.appendChild(excerpt);
+            list.appendChild(item);
+        });
+    }
+
+    function search(query, index) {
+        var tokens = tokenize(query);
+        var matchCounts = {};
+
+        tokens.forEach(function (token) {
+            (index.index[token] || []).forEach(function (docId) {
+                matchCounts[docId] = (matchCounts[docId] || 0) + 1;
+            });
+        });
+
+        return Object.keys(matchCounts)
+            .sort(function (a, b) { return matchCounts[b] - matchCounts[a]; })
+            .map(Number);
+    }
+
+    fetch("searchindex.json")
+        .then(function (response) { return response.json(); })
+        .then(function (index) {
+            var input = document.getElementById("mdmake-search-input");
+            if (!input) return;
+            input.addEventListener("input", function () {
+                render(search(input.value, index), index);
+            });
+        });
+})();
+"#;
+
+/// Standalone search page dropped alongside `search.js`; a site can also embed the same two
+/// elements (`mdmake-search-input`/`mdmake-search-results`) in another page at the output root
+/// and include `search.js`. `search.js` fetches `searchindex.json` and links results relative to
+/// the embedding page, so pages in subdirectories do not find the index.
+const SEARCH_HTML: &str = r#"<head>
+<title>Search</title>
+</head>
+<body>
+<input id="mdmake-search-input" type="search" placeholder="Search...">
+<ul id="mdmake-search-results"></ul>
+<script src="search.js"></script>
+</body>
+"#;
@@ -0,0 +1,148 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::Config;
+
+/// Injected into every generated `<body>` when running under `serve`; reloads the page once the
+/// dev server reports a rebuild.
+pub const RELOAD_SCRIPT: &str = r#"<script>
+(function () {
+    var source = new EventSource("/__mdmake/reload");
+    source.onmessage = function () { location.reload(); };
+})();
+</script>
+"#;
+
+/// Binds `address:port` and serves `config.output_dir`, defaulting to `index.html` for directory
+/// requests and a real 404 page for misses. Blocks forever, handling one connection per thread.
+pub fn run(config: &Config, address: &str, port: u16, generation: Arc<AtomicU64>) {
+    let listener = TcpListener::bind((address, port))
+        .unwrap_or_else(|err| panic!("Failed to bind dev server to {address}:{port}: {err}"));
+
+    println!(
+        "Serving '{}' on http://{address}:{port}",
+        config.output_dir.display()
+    );
+
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        let output_dir = config.output_dir.clone();
+        let generation = generation.clone();
+        thread::spawn(move || handle_connection(stream, &output_dir, generation));
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, output_dir: &Path, generation: Arc<AtomicU64>) {
+    let mut reader = BufReader::new(match stream.try_clone() {
+        Ok(clone) => clone,
+        Err(_) => return,
+    });
+
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() || request_line.is_empty() {
+        return;
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let path = parts.next().unwrap_or("/");
+
+    if method != "GET" {
+        write_response(&mut stream, "405 Method Not Allowed", "text/plain", b"");
+        return;
+    }
+
+    if path == "/__mdmake/reload" {
+        serve_reload_events(stream, generation);
+        return;
+    }
+
+    serve_file(stream, output_dir, path);
+}
+
+fn serve_reload_events(mut stream: TcpStream, generation: Arc<AtomicU64>) {
+    let header = "HTTP/1.1 200 OK\r\n\
+         Content-Type: text/event-stream\r\n\
+         Cache-Control: no-cache\r\n\
+         Connection: keep-alive\r\n\r\n";
+
+    if stream.write_all(header.as_bytes()).is_err() {
+        return;
+    }
+
+    let mut last_seen = generation.load(Ordering::SeqCst);
+    loop {
+        let current = generation.load(Ordering::SeqCst);
+        if current != last_seen {
+            last_seen = current;
+            if stream.write_all(b"data: reload\n\n").is_err() {
+                return;
+            }
+            if stream.flush().is_err() {
+                return;
+            }
+        }
+        thread::sleep(Duration::from_millis(300));
+    }
+}
+
+fn serve_file(mut stream: TcpStream, output_dir: &Path, request_path: &str) {
+    let requested = request_path.split(['?', '#']).next().unwrap_or("/");
+    let relative = requested.trim_start_matches('/');
+
+    let mut file_path = output_dir.join(relative);
+    if file_path.is_dir() || relative.is_empty() {
+        file_path = file_path.join("index.html");
+    }
+
+    match std::fs::read(&file_path) {
+        Ok(contents) => {
+            let content_type = content_type_for(&file_path);
+            write_response(&mut stream, "200 OK", content_type, &contents);
+        }
+        Err(_) => {
+            let not_found_page = output_dir.join("404.html");
+            let contents = std::fs::read(&not_found_page)
+                .unwrap_or_else(|_| b"<h1>404 Not Found</h1>".to_vec());
+            write_response(&mut stream, "404 Not Found", "text/html", &contents);
+        }
+    }
+}
+
+fn content_type_for(path: &PathBuf) -> &'static str {
+    match path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+        .as_str()
+    {
+        "html" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" => "text/javascript; charset=utf-8",
+        "json" => "application/json",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "ico" => "image/x-icon",
+        _ => "application/octet-stream",
+    }
+}
+
+fn write_response(stream: &mut TcpStream, status: &str, content_type: &str, body: &[u8]) {
+    let header = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\r\n",
+        body.len()
+    );
+
+    if stream.write_all(header.as_bytes()).is_err() {
+        return;
+    }
+    let _ = stream.write_all(body);
+}
@@ -0,0 +1,203 @@
+use std::path::{Path, PathBuf};
+
+use markdown::{mdast, ParseOptions};
+
+use crate::StringContents;
+
+/// One entry in the site's reading order: a page plus any nested sub-pages.
+#[derive(Debug, Clone)]
+pub struct PageEntry {
+    pub title: String,
+    pub url: String,
+    pub children: Vec<PageEntry>,
+}
+
+/// Builds the site's page structure from `input_dir/SUMMARY.md` if present, falling back to the
+/// markdown files found by `walk_dir`, sorted alphabetically by path (flat, no nesting) otherwise.
+pub fn load(input_dir: &Path) -> Vec<PageEntry> {
+    let summary_path = input_dir.join("SUMMARY.md");
+    match std::fs::read_to_string(&summary_path) {
+        Ok(contents) => parse_summary(&contents),
+        Err(_) => fallback_structure(input_dir),
+    }
+}
+
+fn parse_summary(contents: &str) -> Vec<PageEntry> {
+    let Ok(ast) = markdown::to_mdast(contents, &ParseOptions::default()) else {
+        return Vec::new();
+    };
+
+    let Some(children) = ast.children() else {
+        return Vec::new();
+    };
+
+    children
+        .iter()
+        .filter_map(|node| match node {
+            mdast::Node::List(list) => Some(list_to_entries(list)),
+            _ => None,
+        })
+        .flatten()
+        .collect()
+}
+
+fn list_to_entries(list: &mdast::List) -> Vec<PageEntry> {
+    list.children
+        .iter()
+        .filter_map(|child| match child {
+            mdast::Node::ListItem(item) => Some(list_item_to_entry(item)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn list_item_to_entry(item: &mdast::ListItem) -> PageEntry {
+    let mut title = String::new();
+    let mut href = String::new();
+    let mut children = Vec::new();
+
+    for child in &item.children {
+        match child {
+            mdast::Node::Paragraph(p) => {
+                if let Some(link) = find_link(&p.children) {
+                    title = link.children.get_string_contents();
+                    href = link.url.clone();
+                }
+            }
+            mdast::Node::Link(link) => {
+                title = link.children.get_string_contents();
+                href = link.url.clone();
+            }
+            mdast::Node::List(nested) => children = list_to_entries(nested),
+            _ => {}
+        }
+    }
+
+    PageEntry {
+        title,
+        url: md_href_to_output_url(&href),
+        children,
+    }
+}
+
+fn find_link(nodes: &[mdast::Node]) -> Option<&mdast::Link> {
+    nodes.iter().find_map(|node| match node {
+        mdast::Node::Link(link) => Some(link),
+        _ => None,
+    })
+}
+
+fn md_href_to_output_url(href: &str) -> String {
+    let mut path = PathBuf::from(href);
+    if path.extension().map(|ext| ext.to_ascii_lowercase()) == Some("md".into()) {
+        path = path.with_extension("html");
+    }
+    path.to_str().unwrap_or(href).to_string()
+}
+
+/// Alphabetical, flat fallback used when no `SUMMARY.md` exists: the markdown files found by
+/// `walk_dir`, sorted by path.
+fn fallback_structure(input_dir: &Path) -> Vec<PageEntry> {
+    let mut paths = crate::walk_dir(&input_dir.to_path_buf()).unwrap_or_default();
+    paths.retain(|path| path.extension().map(|ext| ext.to_ascii_lowercase()) == Some("md".into()));
+    paths.sort();
+
+    paths
+        .iter()
+        .map(|path| {
+            let relative = path.strip_prefix(input_dir).unwrap_or(path);
+            let url = relative
+                .with_extension("html")
+                .to_str()
+                .unwrap_or_default()
+                .to_string();
+            let title = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or_default()
+                .to_string();
+
+            PageEntry {
+                title,
+                url,
+                children: Vec::new(),
+            }
+        })
+        .collect()
+}
+
+/// Flattens the page tree into reading order (depth-first), for computing prev/next neighbors.
+fn flatten(entries: &[PageEntry]) -> Vec<&PageEntry> {
+    fn walk<'a>(entries: &'a [PageEntry], flat: &mut Vec<&'a PageEntry>) {
+        for entry in entries {
+            flat.push(entry);
+            walk(&entry.children, flat);
+        }
+    }
+
+    let mut flat = Vec::new();
+    walk(entries, &mut flat);
+    flat
+}
+
+/// Renders a nested `<nav><ul>` sidebar over the whole site, linking each entry relative to the
+/// current page via `relative_root`.
+pub fn render_sidebar(entries: &[PageEntry], relative_root: &str) -> String {
+    if entries.is_empty() {
+        return String::new();
+    }
+
+    format!(
+        "<nav class=\"sidebar\">\n{}</nav>\n",
+        render_list(entries, relative_root)
+    )
+}
+
+fn render_list(entries: &[PageEntry], relative_root: &str) -> String {
+    let mut out = String::from("<ul>\n");
+    for entry in entries {
+        out.push_str(&format!(
+            r#"<li><a href="{relative_root}{}">{}</a>"#,
+            entry.url, entry.title
+        ));
+        if !entry.children.is_empty() {
+            out.push_str(&render_list(&entry.children, relative_root));
+        }
+        out.push_str("</li>\n");
+    }
+    out.push_str("</ul>\n");
+    out
+}
+
+/// Renders prev/next links for `current_url`'s position in the page graph's reading order.
+/// Returns an empty string if `current_url` isn't part of the structure (e.g. it was excluded
+/// from `SUMMARY.md`).
+pub fn render_prev_next(entries: &[PageEntry], current_url: &str, relative_root: &str) -> String {
+    let flat = flatten(entries);
+    let Some(index) = flat.iter().position(|entry| entry.url == current_url) else {
+        return String::new();
+    };
+
+    let prev = index.checked_sub(1).and_then(|i| flat.get(i));
+    let next = flat.get(index + 1);
+
+    if prev.is_none() && next.is_none() {
+        return String::new();
+    }
+
+    let mut out = String::from(r#"<footer class="page-nav">"#);
+    if let Some(prev) = prev {
+        out.push_str(&format!(
+            r#"<a class="prev" href="{relative_root}{}">« {}</a>"#,
+            prev.url, prev.title
+        ));
+    }
+    if let Some(next) = next {
+        out.push_str(&format!(
+            r#"<a class="next" href="{relative_root}{}">{} »</a>"#,
+            next.url, next.title
+        ));
+    }
+    out.push_str("</footer>\n");
+    out
+}
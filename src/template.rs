@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use handlebars::Handlebars;
+use serde_json::json;
+
+/// The registry of `*.hbs` page templates loaded from `templates/` under `input_dir`.
+#[derive(Clone)]
+pub struct Templates {
+    registry: Handlebars<'static>,
+}
+
+impl std::fmt::Debug for Templates {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Templates").finish_non_exhaustive()
+    }
+}
+
+impl Templates {
+    /// Loads every `*.hbs` file in `templates_dir`, registered under its file stem. Missing or
+    /// unreadable directories simply yield an empty registry.
+    pub fn load(templates_dir: &Path) -> Self {
+        let mut registry = Handlebars::new();
+
+        if let Ok(entries) = std::fs::read_dir(templates_dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().and_then(|ext| ext.to_str()) != Some("hbs") {
+                    continue;
+                }
+
+                let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                    continue;
+                };
+
+                if let Ok(source) = std::fs::read_to_string(&path) {
+                    let _ = registry.register_template_string(name, source);
+                }
+            }
+        }
+
+        Templates { registry }
+    }
+
+    /// Renders `name` with the page's `{{title}}`, `{{content}}`, `{{toc}}`, `{{relative_root}}`,
+    /// `{{sidebar}}`, `{{prev_next}}` and front-matter fields. Returns `None` if `name` isn't a
+    /// registered template.
+    pub fn render(
+        &self,
+        name: &str,
+        title: &str,
+        content: &str,
+        toc: &str,
+        relative_root: &str,
+        sidebar: &str,
+        prev_next: &str,
+        fields: &HashMap<String, String>,
+    ) -> Option<String> {
+        if !self.registry.has_template(name) {
+            return None;
+        }
+
+        let mut context = json!({
+            "title": title,
+            "content": content,
+            "toc": toc,
+            "relative_root": relative_root,
+            "sidebar": sidebar,
+            "prev_next": prev_next,
+        });
+
+        if let Some(object) = context.as_object_mut() {
+            for (key, value) in fields {
+                object.entry(key.clone()).or_insert_with(|| json!(value));
+            }
+        }
+
+        self.registry.render(name, &context).ok()
+    }
+}
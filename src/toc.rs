@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+
+use markdown::mdast;
+
+use crate::StringContents;
+
+/// Marker users can place in their markdown to control where the TOC is injected; when absent,
+/// the TOC is prepended to the page's rendered markdown, after any header and sidebar. Only pages
+/// built without a Handlebars template handle the marker; templated pages place the TOC through
+/// `{{toc}}` and keep the marker text as written.
+pub const MARKER: &str = "[[toc]]";
+
+pub struct Heading {
+    pub depth: u8,
+    pub text: String,
+    pub slug: String,
+}
+
+/// Walks every `Heading` with `depth >= 2` in document order, assigning each a unique slug.
+pub fn collect_headings(ast: &mdast::Node) -> Vec<Heading> {
+    let mut headings = Vec::new();
+    let mut seen_slugs: HashMap<String, u32> = HashMap::new();
+
+    fn walk(node: &mdast::Node, headings: &mut Vec<Heading>, seen_slugs: &mut HashMap<String, u32>) {
+        if let mdast::Node::Heading(h) = node {
+            if h.depth >= 2 {
+                let text = h.children.get_string_contents();
+                let slug = unique_slug(&slugify(&text), seen_slugs);
+                headings.push(Heading {
+                    depth: h.depth,
+                    text,
+                    slug,
+                });
+            }
+        }
+        if let Some(children) = node.children() {
+            for child in children {
+                walk(child, headings, seen_slugs);
+            }
+        }
+    }
+
+    walk(ast, &mut headings, &mut seen_slugs);
+    headings
+}
+
+fn slugify(text: &str) -> String {
+    let mut slug = String::with_capacity(text.len());
+    let mut last_was_dash = true;
+
+    for ch in text.to_lowercase().chars() {
+        if ch.is_alphanumeric() {
+            slug.push(ch);
+            last_was_dash = false;
+        } else if !last_was_dash {
+            slug.push('-');
+            last_was_dash = true;
+        }
+    }
+
+    slug.trim_end_matches('-').to_string()
+}
+
+fn unique_slug(base: &str, seen_slugs: &mut HashMap<String, u32>) -> String {
+    let count = seen_slugs.entry(base.to_string()).or_insert(0);
+    let slug = if *count == 0 {
+        base.to_string()
+    } else {
+        format!("{base}-{count}")
+    };
+    *count += 1;
+    slug
+}
+
+/// Renders a nested `<nav><ul>` from the flat, depth-ordered `headings` list, nesting correctly
+/// even when a level is skipped (e.g. an h2 directly followed by an h4).
+pub fn render_nav(headings: &[Heading]) -> String {
+    if headings.is_empty() {
+        return String::new();
+    }
+
+    let mut nav = String::from("<nav class=\"toc\">\n<ul>\n");
+    let mut depth_stack = vec![headings[0].depth];
+
+    for (i, heading) in headings.iter().enumerate() {
+        while depth_stack.len() > 1 && heading.depth < *depth_stack.last().unwrap() {
+            nav.push_str("</li></ul>\n");
+            depth_stack.pop();
+        }
+
+        if heading.depth > *depth_stack.last().unwrap() {
+            nav.push_str("<ul>\n");
+            depth_stack.push(heading.depth);
+        } else if i > 0 {
+            nav.push_str("</li>\n");
+        }
+
+        nav.push_str(&format!(
+            r#"<li><a href="#{}">{}</a>"#,
+            heading.slug, heading.text
+        ));
+    }
+
+    for _ in &depth_stack {
+        nav.push_str("</li></ul>\n");
+    }
+    nav.push_str("</nav>\n");
+
+    nav
+}
+
+/// Injects `id="slug"` onto each rendered `<h2>`..`<h6>` tag, in the same document order the
+/// headings were collected in (`markdown::to_html` does not emit ids on its own).
+pub fn inject_heading_ids(html: &str, headings: &[Heading]) -> String {
+    let heading_re = regex::Regex::new(r"(?s)<h([2-6])>(.*?)</h([2-6])>").unwrap();
+    let mut headings = headings.iter();
+
+    heading_re
+        .replace_all(html, |caps: &regex::Captures| {
+            let Some(heading) = headings.next() else {
+                return caps[0].to_string();
+            };
+            format!(
+                r#"<h{d} id="{slug}">{text}</h{d}>"#,
+                d = &caps[1],
+                slug = heading.slug,
+                text = &caps[2]
+            )
+        })
+        .to_string()
+}
@@ -0,0 +1,81 @@
+use std::fs;
+use std::path::Path;
+use std::sync::mpsc::channel;
+
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+
+use crate::{compile_file, frontmatter, Config};
+
+/// Watches `config.input_dir` for filesystem events and recompiles affected markdown files (or
+/// removes their output) as they occur. Calls `on_change` after each recompile/removal — `watch`
+/// mode ignores it, `serve` mode uses it to bump the live-reload generation counter.
+pub fn watch(config: &Config, mut on_change: impl FnMut()) {
+    let (tx, rx) = channel();
+
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+        if let Ok(event) = event {
+            let _ = tx.send(event);
+        }
+    })
+    .expect("Failed to start filesystem watcher.");
+
+    watcher
+        .watch(&config.input_dir, RecursiveMode::Recursive)
+        .expect("Failed to watch input directory for changes.");
+
+    for event in rx {
+        let mut changed = false;
+
+        for path in &event.paths {
+            changed |= match event.kind {
+                EventKind::Remove(_) => handle_removed(path, config),
+                EventKind::Create(_) | EventKind::Modify(_) => handle_changed(path, config),
+                _ => false,
+            };
+        }
+
+        if changed {
+            on_change();
+        }
+    }
+}
+
+fn handle_changed(path: &Path, config: &Config) -> bool {
+    if !path.is_file() || path.extension().and_then(|ext| ext.to_str()) != Some("md") {
+        return false;
+    }
+
+    if path.strip_prefix(&config.input_dir).is_err() {
+        return false;
+    }
+
+    let is_draft =
+        fs::read_to_string(path).is_ok_and(|content| frontmatter::is_draft(&content));
+    if is_draft && !config.drafts {
+        return false;
+    }
+
+    println!("Recompiling: {}", path.display());
+    compile_file(path.to_path_buf(), config);
+    true
+}
+
+fn handle_removed(path: &Path, config: &Config) -> bool {
+    let Ok(relative) = path.strip_prefix(&config.input_dir) else {
+        return false;
+    };
+
+    let output_relative = if relative.extension().and_then(|ext| ext.to_str()) == Some("md") {
+        relative.with_extension("html")
+    } else {
+        relative.to_path_buf()
+    };
+    let output_path = config.output_dir.join(output_relative);
+
+    if std::fs::remove_file(&output_path).is_ok() {
+        println!("Removed: {}", output_path.display());
+        true
+    } else {
+        false
+    }
+}